@@ -1,4 +1,6 @@
 use image::GenericImageView;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
@@ -14,9 +16,85 @@ pub struct ImageMetadata {
     pub size: u64,
 }
 
+/// File extensions handled by the RAW decode path via imagepipe.
+const RAW_EXTENSIONS: &[&str] = &[
+    "nef", "cr2", "cr3", "arw", "dng", "rw2", "orf", "raf", "pef", "srw",
+];
+
+/// Decode an image from disk, falling back to specialised decoders for camera
+/// RAW files and HEIC/HEIF images that `image::open` cannot read. Everything
+/// is normalised to a `DynamicImage` so the resize/encode path is shared.
+fn decode_image(path: &str) -> Result<image::DynamicImage, String> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some(ext) if RAW_EXTENSIONS.contains(&ext) => decode_raw(path),
+        Some("heic") | Some("heif") => decode_heif(path),
+        _ => image::open(path).map_err(|e| e.to_string()),
+    }
+}
+
+/// Decode a camera RAW file into an 8-bit RGB image using imagepipe's pipeline.
+fn decode_raw(path: &str) -> Result<image::DynamicImage, String> {
+    let mut pipeline = imagepipe::Pipeline::new_from_file(path).map_err(|e| e.to_string())?;
+    let decoded = pipeline.output_8bit(None).map_err(|e| e.to_string())?;
+
+    let buffer = image::ImageBuffer::<image::Rgb<u8>, _>::from_raw(
+        decoded.width as u32,
+        decoded.height as u32,
+        decoded.data,
+    )
+    .ok_or_else(|| "RAW decode produced an unexpected buffer size".to_string())?;
+
+    Ok(image::DynamicImage::ImageRgb8(buffer))
+}
+
+/// Decode a HEIC/HEIF image via libheif into an 8-bit RGB image.
+#[cfg(feature = "heif")]
+fn decode_heif(path: &str) -> Result<image::DynamicImage, String> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let lib_heif = LibHeif::new();
+    let ctx = HeifContext::read_from_file(path).map_err(|e| e.to_string())?;
+    let handle = ctx.primary_image_handle().map_err(|e| e.to_string())?;
+    let image = lib_heif
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgb), None)
+        .map_err(|e| e.to_string())?;
+
+    let width = image.width();
+    let height = image.height();
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or_else(|| "HEIF image missing interleaved RGB plane".to_string())?;
+
+    // The plane stride may exceed width * 3, so copy row by row into a tight buffer.
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+    let row_bytes = (width * 3) as usize;
+    for row in 0..height as usize {
+        let start = row * plane.stride;
+        data.extend_from_slice(&plane.data[start..start + row_bytes]);
+    }
+
+    let buffer = image::ImageBuffer::<image::Rgb<u8>, _>::from_raw(width, height, data)
+        .ok_or_else(|| "HEIF decode produced an unexpected buffer size".to_string())?;
+
+    Ok(image::DynamicImage::ImageRgb8(buffer))
+}
+
+/// Stub used when the `heif` feature is disabled so HEIC/HEIF files fail with
+/// a clear message instead of a build error.
+#[cfg(not(feature = "heif"))]
+fn decode_heif(_path: &str) -> Result<image::DynamicImage, String> {
+    Err("HEIC/HEIF support requires the `heif` feature to be enabled".to_string())
+}
+
 #[tauri::command]
 pub fn get_image_metadata(path: String) -> Result<ImageMetadata, String> {
-    let img = image::open(&path).map_err(|e| e.to_string())?;
+    let img = decode_image(&path)?;
     let dimensions = img.dimensions();
     let metadata = std::fs::metadata(&path).map_err(|e| e.to_string())?;
 
@@ -56,70 +134,318 @@ pub struct ImageToSave {
     /// - 1-49 = low quality, maximum compression
     /// Only applies to JPEG and WebP formats
     pub quality: u8,
+    /// oxipng optimization preset (0-6) applied to PNG output.
+    /// Higher levels compress harder (and slower); level 6 additionally
+    /// switches the deflater to Zopfli. Ignored for non-PNG output.
+    #[serde(default)]
+    pub png_optimization_level: u8,
+}
+
+/// Per-image outcome so the UI can show a before/after table.
+#[derive(Serialize)]
+pub struct SavedImageInfo {
+    pub source_path: String,
+    pub destination_path: String,
+    /// Size of the original file in bytes
+    pub original_size: u64,
+    /// Size of the written, compressed file in bytes
+    pub new_size: u64,
+    pub width: u32,
+    pub height: u32,
+    /// Compression ratio, `new_size / original_size` (smaller is better)
+    pub compression_ratio: f64,
 }
 
 #[derive(Serialize)]
 pub struct SaveResult {
     pub success: bool,
     pub saved_count: usize,
+    /// Number of images that failed to process
+    pub failed_count: usize,
+    /// Error messages for the images that failed, one per failure
+    pub errors: Vec<String>,
+    /// Detailed before/after information for each successfully saved image
+    pub saved: Vec<SavedImageInfo>,
+}
+
+/// Resize, encode, and write a single image to its destination path.
+/// Extracted so the batch can run each image independently in parallel.
+fn process_image(image_info: &ImageToSave) -> Result<SavedImageInfo, String> {
+    let (bytes, width, height) = encode_image(image_info)?;
+
+    let dest_file_path = Path::new(&image_info.destination_path);
+
+    // Ensure parent directory exists
+    if let Some(parent) = dest_file_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    std::fs::write(dest_file_path, &bytes).map_err(|e| e.to_string())?;
+
+    let original_size = std::fs::metadata(&image_info.path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let new_size = bytes.len() as u64;
+    let compression_ratio = if original_size > 0 {
+        new_size as f64 / original_size as f64
+    } else {
+        0.0
+    };
+
+    Ok(SavedImageInfo {
+        source_path: image_info.path.clone(),
+        destination_path: image_info.destination_path.clone(),
+        original_size,
+        new_size,
+        width,
+        height,
+        compression_ratio,
+    })
+}
+
+/// Decode, resize, and encode an image entirely in memory, returning the
+/// encoded bytes along with the output dimensions. Shared by the loose-file
+/// and archive save paths so both honour the same format handling.
+fn encode_image(image_info: &ImageToSave) -> Result<(Vec<u8>, u32, u32), String> {
+    // STEP 1: Open and decode the source image (RAW/HEIF aware)
+    let img = decode_image(&image_info.path)?;
+
+    // STEP 2: Resize the image
+    let resized = img.resize_exact(
+        image_info.target_width,
+        image_info.target_height,
+        image::imageops::FilterType::Lanczos3,
+    );
+    let (width, height) = resized.dimensions();
+
+    let dest_path = Path::new(&image_info.destination_path);
+
+    // STEP 3: Encode based on the requested output format
+    let bytes = match &image_info.output_format {
+        OutputFormat::Webp => {
+            let rgba_image = resized.to_rgba8();
+            let (w, h) = rgba_image.dimensions();
+
+            let encoder = webp::Encoder::from_rgba(rgba_image.as_raw(), w, h);
+
+            let webp_data = if image_info.quality == 100 {
+                encoder.encode_lossless()
+            } else {
+                encoder.encode(image_info.quality as f32)
+            };
+
+            webp_data.to_vec()
+        }
+
+        OutputFormat::Png => encode_optimized_png(&resized, image_info.png_optimization_level)?,
+
+        OutputFormat::KeepOriginal => {
+            // KeepOriginal on a PNG source still benefits from the lossless pass.
+            let is_png = dest_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.eq_ignore_ascii_case("png"))
+                .unwrap_or(false);
+
+            if is_png {
+                encode_optimized_png(&resized, image_info.png_optimization_level)?
+            } else {
+                let format = image::ImageFormat::from_path(dest_path).map_err(|e| e.to_string())?;
+                encode_to_format(&resized, format)?
+            }
+        }
+
+        OutputFormat::Jpeg => {
+            let rgb_image = resized.to_rgb8();
+            let mut buffer = Vec::new();
+            let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut buffer,
+                image_info.quality.clamp(1, 100),
+            );
+            encoder.encode_image(&rgb_image).map_err(|e| e.to_string())?;
+            buffer
+        }
+    };
+
+    Ok((bytes, width, height))
+}
+
+/// Encode a resized image to the given format into an in-memory buffer.
+fn encode_to_format(
+    resized: &image::DynamicImage,
+    format: image::ImageFormat,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    resized
+        .write_to(&mut buffer, format)
+        .map_err(|e| e.to_string())?;
+    Ok(buffer.into_inner())
+}
+
+/// Encode a resized image to PNG in memory and run it through oxipng for a
+/// lossless size reduction. Metadata chunks are stripped and the highest
+/// preset upgrades the deflater to Zopfli.
+fn encode_optimized_png(resized: &image::DynamicImage, level: u8) -> Result<Vec<u8>, String> {
+    let png_bytes = encode_to_format(resized, image::ImageFormat::Png)?;
+
+    let mut options = oxipng::Options::from_preset(level.min(6));
+    options.strip = oxipng::StripChunks::Safe;
+    if level >= 6 {
+        options.deflate = oxipng::Deflaters::Zopfli {
+            iterations: std::num::NonZeroU8::new(15).unwrap(),
+        };
+    }
+
+    oxipng::optimize_from_memory(&png_bytes, &options).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn save_images(
     images: Vec<ImageToSave>,
+    thread_count: Option<usize>,
 ) -> Result<SaveResult, String> {
-    let mut saved_count = 0;
-    
-    for image_info in images {
-        // STEP 1: Open and decode the source image
-        let img = image::open(&image_info.path).map_err(|e| e.to_string())?;
-        
-        // STEP 2: Resize the image
-        let resized = img.resize_exact(
-            image_info.target_width,
-            image_info.target_height,
-            image::imageops::FilterType::Lanczos3,
-        );
-        
-        let dest_file_path = Path::new(&image_info.destination_path);
-        
-        // Ensure parent directory exists
-        if let Some(parent) = dest_file_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    // Cap the number of workers; default to all available cores.
+    let threads = thread_count.unwrap_or_else(num_cpus::get).max(1);
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // Process every image concurrently, collecting a Result per image so a
+    // single failure doesn't abort the rest of the batch.
+    let results: Vec<Result<SavedImageInfo, String>> =
+        pool.install(|| images.par_iter().map(process_image).collect());
+
+    let mut saved = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(info) => saved.push(info),
+            Err(e) => errors.push(e),
         }
-        
-        // STEP 3: Encode and save based on format
-        match &image_info.output_format {
-            OutputFormat::Webp => {
-                let rgba_image = resized.to_rgba8();
-                let (width, height) = rgba_image.dimensions();
-                
-                let encoder = webp::Encoder::from_rgba(
-                    rgba_image.as_raw(),
-                    width,
-                    height
-                );
-                
-                let webp_data = if image_info.quality == 100 {
-                    encoder.encode_lossless()
-                } else {
-                    encoder.encode(image_info.quality as f32)
-                };
-                
-                std::fs::write(&dest_file_path, &*webp_data).map_err(|e| e.to_string())?;
+    }
+
+    Ok(SaveResult {
+        success: errors.is_empty(),
+        saved_count: saved.len(),
+        failed_count: errors.len(),
+        errors,
+        saved,
+    })
+}
+
+/// Optional compression applied to the output archive.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveCompression {
+    /// Wrap the tar stream in an lz4 frame (produces a `.tar.lz4`).
+    Lz4,
+}
+
+/// Resize and encode every image, then stream them into a single tar archive
+/// at `archive_path` instead of writing loose files. With `compression` set to
+/// `Lz4` the tar stream is wrapped in an lz4 frame encoder (behind the `lz4`
+/// feature), giving a one-click "export all" as a `.tar` or `.tar.lz4`.
+#[tauri::command]
+pub fn save_images_to_archive(
+    images: Vec<ImageToSave>,
+    archive_path: String,
+    compression: Option<ArchiveCompression>,
+) -> Result<SaveResult, String> {
+    let archive_file_path = Path::new(&archive_path);
+    if let Some(parent) = archive_file_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file = std::fs::File::create(archive_file_path).map_err(|e| e.to_string())?;
+
+    match compression {
+        Some(ArchiveCompression::Lz4) => {
+            #[cfg(feature = "lz4")]
+            {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(file);
+                let result = write_archive(&mut encoder, &images)?;
+                encoder.finish().map_err(|e| e.to_string())?;
+                Ok(result)
             }
-            
-            OutputFormat::KeepOriginal | OutputFormat::Png | OutputFormat::Jpeg => {
-                resized.save(&dest_file_path).map_err(|e| e.to_string())?;
+            #[cfg(not(feature = "lz4"))]
+            {
+                Err("lz4 archive compression requires the `lz4` feature to be enabled".to_string())
             }
         }
-        
-        saved_count += 1;
+        None => write_archive(file, &images),
     }
-    
+}
+
+/// Encode each image in memory and append it to a tar archive written to
+/// `writer`, collecting per-image results exactly like `save_images`.
+fn write_archive<W: std::io::Write>(
+    writer: W,
+    images: &[ImageToSave],
+) -> Result<SaveResult, String> {
+    let mut builder = tar::Builder::new(writer);
+
+    let mut saved = Vec::new();
+    let mut errors = Vec::new();
+
+    for image_info in images {
+        match append_image(&mut builder, image_info) {
+            Ok(info) => saved.push(info),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    builder.finish().map_err(|e| e.to_string())?;
+
     Ok(SaveResult {
-        success: true,
-        saved_count,
+        success: errors.is_empty(),
+        saved_count: saved.len(),
+        failed_count: errors.len(),
+        errors,
+        saved,
+    })
+}
+
+/// Encode a single image and append it to the tar archive under the file name
+/// taken from its `destination_path`.
+fn append_image<W: std::io::Write>(
+    builder: &mut tar::Builder<W>,
+    image_info: &ImageToSave,
+) -> Result<SavedImageInfo, String> {
+    let (bytes, width, height) = encode_image(image_info)?;
+
+    // Use just the file name from the destination so the archive stays flat.
+    let name = Path::new(&image_info.destination_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| image_info.destination_path.clone());
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+
+    builder
+        .append_data(&mut header, &name, bytes.as_slice())
+        .map_err(|e| e.to_string())?;
+
+    let original_size = std::fs::metadata(&image_info.path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let new_size = bytes.len() as u64;
+    let compression_ratio = if original_size > 0 {
+        new_size as f64 / original_size as f64
+    } else {
+        0.0
+    };
+
+    Ok(SavedImageInfo {
+        source_path: image_info.path.clone(),
+        destination_path: image_info.destination_path.clone(),
+        original_size,
+        new_size,
+        width,
+        height,
+        compression_ratio,
     })
 }
 
@@ -212,6 +538,91 @@ pub fn list_directory(path: String) -> Result<Vec<DirectoryEntry>, String> {
     Ok(entries)
 }
 
+/// Image file extensions surfaced by the recursive scan (plus RAW formats).
+const SCAN_IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "gif", "bmp", "tiff", "tif", "heic", "heif",
+];
+
+/// A discovered image file enriched with its dimensions and byte size.
+#[derive(Serialize)]
+pub struct ImageEntry {
+    pub name: String,
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    pub size: u64,
+}
+
+/// Recursively walk `path`, returning image files whose extension is in the
+/// allowed set (standard formats plus camera RAW) and not in
+/// `excluded_extensions`. `max_depth` caps how deep the walk descends. Each
+/// entry is enriched with width/height and byte size so the UI can populate a
+/// batch-compression queue from a whole photo tree at once.
+#[tauri::command]
+pub fn scan_images_recursive(
+    path: String,
+    excluded_extensions: Vec<String>,
+    max_depth: Option<usize>,
+) -> Result<Vec<ImageEntry>, String> {
+    let dir_path = Path::new(&path);
+    if !dir_path.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    let excluded: Vec<String> = excluded_extensions
+        .iter()
+        .map(|ext| ext.trim_start_matches('.').to_ascii_lowercase())
+        .collect();
+
+    let mut walker = walkdir::WalkDir::new(dir_path);
+    if let Some(depth) = max_depth {
+        walker = walker.max_depth(depth);
+    }
+
+    // Collect the candidate image paths first, then enrich them in parallel.
+    let candidates: Vec<std::path::PathBuf> = walker
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|file_path| {
+            let ext = file_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.to_ascii_lowercase());
+            match ext {
+                Some(ext) => {
+                    let allowed = SCAN_IMAGE_EXTENSIONS.contains(&ext.as_str())
+                        || RAW_EXTENSIONS.contains(&ext.as_str());
+                    allowed && !excluded.contains(&ext)
+                }
+                None => false,
+            }
+        })
+        .collect();
+
+    let mut entries: Vec<ImageEntry> = candidates
+        .par_iter()
+        .filter_map(|file_path| {
+            let path_str = file_path.to_string_lossy().to_string();
+            let metadata = get_image_metadata(path_str.clone()).ok()?;
+            let name = file_path.file_name()?.to_string_lossy().to_string();
+
+            Some(ImageEntry {
+                name,
+                path: path_str,
+                width: metadata.width,
+                height: metadata.height,
+                size: metadata.size,
+            })
+        })
+        .collect();
+
+    entries.sort_by(|a, b| a.path.to_lowercase().cmp(&b.path.to_lowercase()));
+
+    Ok(entries)
+}
+
 /// Get the user's home directory path
 #[tauri::command]
 pub fn get_home_directory() -> Result<String, String> {
@@ -228,6 +639,140 @@ pub fn get_parent_directory(path: String) -> Result<String, String> {
     
     let parent = dir_path.parent()
         .ok_or("No parent directory")?;
-    
+
     Ok(parent.to_string_lossy().to_string())
 }
+
+// ============================================================================
+// Perceptual-hash duplicate detection
+// ============================================================================
+
+/// Default Hamming distance at or below which two dHashes count as near-duplicates.
+const DEFAULT_HASH_THRESHOLD: u32 = 10;
+
+/// A single member of a near-duplicate cluster.
+#[derive(Serialize, Clone)]
+pub struct SimilarImage {
+    pub path: String,
+    pub size: u64,
+}
+
+/// Compute the 64-bit "dHash" of an image: resize to 9x8 grayscale and set a
+/// bit for each of the 8 columns per row when the left pixel is brighter than
+/// its right neighbour.
+fn dhash(path: &str) -> Result<u64, String> {
+    let img = decode_image(path)?;
+    let small = img
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash: u64 = 0;
+    for row in 0..8u32 {
+        for col in 0..8u32 {
+            let left = small.get_pixel(col, row)[0];
+            let right = small.get_pixel(col + 1, row)[0];
+            hash <<= 1;
+            if left > right {
+                hash |= 1;
+            }
+        }
+    }
+
+    Ok(hash)
+}
+
+/// Find visually duplicate or near-duplicate images under `path` so users can
+/// drop redundant copies before compressing. Each image is reduced to a 64-bit
+/// perceptual hash (in parallel), then grouped by pairwise Hamming distance at
+/// or below `threshold` (default ~10 bits). Returns only clusters with more
+/// than one member, each carrying its members' path and size.
+#[tauri::command]
+pub fn find_similar_images(
+    path: String,
+    threshold: Option<u32>,
+) -> Result<Vec<Vec<SimilarImage>>, String> {
+    let dir_path = Path::new(&path);
+    if !dir_path.is_dir() {
+        return Err(format!("Path is not a directory: {}", path));
+    }
+
+    let threshold = threshold.unwrap_or(DEFAULT_HASH_THRESHOLD);
+
+    // Gather candidate image files.
+    let candidates: Vec<std::path::PathBuf> = walkdir::WalkDir::new(dir_path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|file_path| {
+            file_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| ext.to_ascii_lowercase())
+                .map(|ext| {
+                    SCAN_IMAGE_EXTENSIONS.contains(&ext.as_str())
+                        || RAW_EXTENSIONS.contains(&ext.as_str())
+                })
+                .unwrap_or(false)
+        })
+        .collect();
+
+    // Hash every image in parallel, dropping ones that fail to decode.
+    let hashed: Vec<(SimilarImage, u64)> = candidates
+        .par_iter()
+        .filter_map(|file_path| {
+            let hash = dhash(&file_path.to_string_lossy()).ok()?;
+            let size = std::fs::metadata(file_path).map(|m| m.len()).unwrap_or(0);
+            Some((
+                SimilarImage {
+                    path: file_path.to_string_lossy().to_string(),
+                    size,
+                },
+                hash,
+            ))
+        })
+        .collect();
+
+    // Union-find over the O(n^2) pairwise comparisons to build clusters.
+    let mut parent: Vec<usize> = (0..hashed.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        // Path compression.
+        let mut cur = i;
+        while parent[cur] != root {
+            let next = parent[cur];
+            parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+
+    for i in 0..hashed.len() {
+        for j in (i + 1)..hashed.len() {
+            if (hashed[i].1 ^ hashed[j].1).count_ones() <= threshold {
+                let ri = find(&mut parent, i);
+                let rj = find(&mut parent, j);
+                if ri != rj {
+                    parent[ri] = rj;
+                }
+            }
+        }
+    }
+
+    // Bucket members by their cluster root.
+    let mut clusters: std::collections::HashMap<usize, Vec<SimilarImage>> =
+        std::collections::HashMap::new();
+    for i in 0..hashed.len() {
+        let root = find(&mut parent, i);
+        clusters.entry(root).or_default().push(hashed[i].0.clone());
+    }
+
+    // Only report clusters that actually contain duplicates.
+    Ok(clusters
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .collect())
+}
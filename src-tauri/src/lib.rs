@@ -14,7 +14,10 @@ pub fn run() {
             commands::greet,
             commands::get_image_metadata,
             commands::save_images,
+            commands::save_images_to_archive,
             commands::list_directory,
+            commands::scan_images_recursive,
+            commands::find_similar_images,
             commands::get_home_directory,
             commands::get_parent_directory
         ])